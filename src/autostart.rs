@@ -0,0 +1,79 @@
+use crate::error_util::AnyhowExt;
+use anyhow::{Context, Result};
+use log::error;
+use std::env;
+
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+#[cfg(target_os = "windows")]
+const RUN_VALUE_NAME: &str = "RimeTool";
+
+#[cfg(target_os = "windows")]
+pub(crate) fn is_enabled() -> bool {
+    use registry::{Hive, Security};
+    Hive::CurrentUser
+        .open(RUN_KEY, Security::Read)
+        .anyhow()
+        .and_then(|key| key.value(RUN_VALUE_NAME).anyhow())
+        .is_ok()
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn set_enabled(enabled: bool) -> Result<()> {
+    use registry::{Data, Hive, Security};
+    let key = Hive::CurrentUser
+        .create(RUN_KEY, Security::Write)
+        .anyhow()?;
+    if enabled {
+        let exe = env::current_exe().with_context(|| "failed to resolve current exe path")?;
+        let exe = exe
+            .to_str()
+            .with_context(|| "current exe path is not valid UTF-8")?;
+        key.set_value(RUN_VALUE_NAME, &Data::String(exe.try_into()?))
+            .anyhow()?;
+    } else {
+        key.delete_value(RUN_VALUE_NAME).anyhow()?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn autostart_desktop_file() -> Option<std::path::PathBuf> {
+    dirs::config_dir().map(|p| p.join("autostart").join("rime-tool.desktop"))
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn is_enabled() -> bool {
+    autostart_desktop_file().is_some_and(|p| p.exists())
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn set_enabled(enabled: bool) -> Result<()> {
+    let path = autostart_desktop_file().with_context(|| "could not resolve config dir")?;
+    if enabled {
+        let exe = env::current_exe().with_context(|| "failed to resolve current exe path")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec={}\nX-GNOME-Autostart-enabled=true\n",
+            crate::NAME,
+            exe.to_string_lossy(),
+        );
+        std::fs::write(&path, contents)?;
+    } else if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+/// Applies the autostart toggle, logging (but not panicking) on failure so a
+/// single misbehaving registry/filesystem doesn't take down the tray app.
+/// Re-syncs `item` to the actual on-disk state afterwards, so a failed
+/// toggle doesn't leave the checkbox showing a state the OS doesn't have.
+pub(crate) fn set_enabled_or_log(enabled: bool, item: &tray_icon::menu::CheckMenuItem) {
+    if let Err(e) = set_enabled(enabled) {
+        error!("failed to update autostart setting: {e}");
+    }
+    item.set_checked(is_enabled());
+}
@@ -0,0 +1,104 @@
+//! Linux deployment backend: drives Rime through whichever input method
+//! frontend (fcitx5 or ibus) is actually hosting it.
+
+use crate::Frontend;
+use anyhow::{bail, Context, Result};
+use std::{ffi::OsStr, process::Command};
+use sysinfo::{ProcessRefreshKind, RefreshKind, System};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ResolvedFrontend {
+    Fcitx5,
+    Ibus,
+}
+
+impl ResolvedFrontend {
+    fn process_name(self) -> &'static str {
+        match self {
+            ResolvedFrontend::Fcitx5 => "fcitx5",
+            ResolvedFrontend::Ibus => "ibus-daemon",
+        }
+    }
+
+    fn restart_command(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            ResolvedFrontend::Fcitx5 => ("fcitx5", &["-r"]),
+            ResolvedFrontend::Ibus => ("ibus", &["restart"]),
+        }
+    }
+}
+
+/// Resolves `Frontend::Auto` to whichever frontend process is actually
+/// running, preferring fcitx5 since it is the more common modern Rime host.
+fn resolve(frontend: Frontend) -> ResolvedFrontend {
+    match frontend {
+        Frontend::Fcitx5 => ResolvedFrontend::Fcitx5,
+        Frontend::Ibus => ResolvedFrontend::Ibus,
+        Frontend::Auto => {
+            let s = System::new_with_specifics(
+                RefreshKind::nothing().with_processes(ProcessRefreshKind::everything()),
+            );
+            if s.processes_by_name(OsStr::new("fcitx5")).next().is_some() {
+                ResolvedFrontend::Fcitx5
+            } else {
+                ResolvedFrontend::Ibus
+            }
+        }
+    }
+}
+
+fn is_running(frontend: ResolvedFrontend) -> bool {
+    let s = System::new_with_specifics(
+        RefreshKind::nothing().with_processes(ProcessRefreshKind::everything()),
+    );
+    s.processes_by_name(OsStr::new(frontend.process_name()))
+        .next()
+        .is_some()
+}
+
+pub(crate) fn redeploy(frontend: Frontend, user_dir: &str, shared_dir: &str) -> Result<()> {
+    let status = Command::new("rime_deployer")
+        .arg("--build")
+        .arg(user_dir)
+        .arg(shared_dir)
+        .status()
+        .with_context(|| "failed to run rime_deployer")?;
+    if !status.success() {
+        bail!("rime_deployer exited with {status}");
+    }
+    let (cmd, args) = resolve(frontend).restart_command();
+    let status = Command::new(cmd)
+        .args(args)
+        .status()
+        .with_context(|| format!("failed to restart {cmd}"))?;
+    if !status.success() {
+        bail!("{cmd} exited with {status}");
+    }
+    Ok(())
+}
+
+pub(crate) fn start_service(frontend: Frontend) -> Result<()> {
+    let resolved = resolve(frontend);
+    if is_running(resolved) {
+        return Ok(());
+    }
+    let name = resolved.process_name();
+    Command::new(name)
+        .spawn()
+        .with_context(|| format!("failed to start {name}"))?;
+    Ok(())
+}
+
+pub(crate) fn stop_service(frontend: Frontend) {
+    let resolved = resolve(frontend);
+    let s = System::new_with_specifics(
+        RefreshKind::nothing().with_processes(ProcessRefreshKind::everything()),
+    );
+    for p in s.processes_by_name(OsStr::new(resolved.process_name())) {
+        p.kill();
+    }
+}
+
+pub(crate) fn get_service_status(frontend: Frontend) -> bool {
+    is_running(resolve(frontend))
+}
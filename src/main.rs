@@ -1,8 +1,15 @@
 #![allow(unused)]
 #![windows_subsystem = "windows"]
 
+mod autostart;
 mod error_util;
+#[cfg(target_os = "linux")]
+mod linux;
+mod watchdog;
+mod watcher;
 use crate::error_util::AnyhowExt;
+use crate::watchdog::Watchdog;
+use crate::watcher::ConfigWatcher;
 use anyhow::{anyhow, Context};
 use log::{debug, error, info, trace, warn, LevelFilter};
 use log4rs::{
@@ -36,6 +43,10 @@ use std::{
     process::Command,
 };
 use std::{ffi::OsStr, fs};
+use std::io::Read as _;
+use std::process::Stdio;
+use std::time::Duration;
+use notify_rust::Notification;
 use sysinfo::{Process, ProcessRefreshKind, RefreshKind, System};
 use tao::event_loop::{ControlFlow, DeviceEventFilter, EventLoopBuilder, EventLoopProxy};
 use toml;
@@ -53,28 +64,128 @@ enum TrayUserEvent {
     ServiceClicked,
     IconClicked,
     UpdateService(bool),
+    ToggleAutoDeploy(bool),
+    ToggleAutostart(bool),
+    ToggleWatchdog(bool),
 }
 
+/// Quiet period the config watcher waits for after the last filesystem event
+/// before it considers the burst of writes finished and triggers a redeploy.
+const AUTO_DEPLOY_QUIET_PERIOD: Duration = Duration::from_millis(1500);
+
 #[derive(Debug, Clone)]
 struct RgbaIcon {
     rgba: Vec<u8>,
     width: u32,
     height: u32,
 }
+
+impl RgbaIcon {
+    /// Returns a desaturated, dimmed copy used to show the tray icon while
+    /// the algorithm service is stopped.
+    fn stopped_variant(&self) -> RgbaIcon {
+        let mut rgba = self.rgba.clone();
+        for px in rgba.chunks_exact_mut(4) {
+            let gray = (px[0] as u32 * 30 + px[1] as u32 * 59 + px[2] as u32 * 11) / 100;
+            let gray = gray as u8;
+            px[0] = gray;
+            px[1] = gray;
+            px[2] = gray;
+            px[3] = ((px[3] as u16 * 2) / 3) as u8;
+        }
+        RgbaIcon {
+            rgba,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
 #[derive(Clone, Debug, Deserialize)]
 struct ToolConfig {
     #[serde(default = "default_rime_root")]
     root: String,
+    #[serde(default = "default_user_dir")]
+    user_dir: String,
+    #[serde(default = "default_shared_dir")]
+    shared_dir: String,
+    #[serde(default = "default_notifications")]
+    notifications: bool,
+    #[serde(default)]
+    icon: Option<String>,
+    #[serde(default)]
+    frontend: Frontend,
 }
 
 impl Default for ToolConfig {
     fn default() -> Self {
         Self {
             root: default_rime_root(),
+            user_dir: default_user_dir(),
+            shared_dir: default_shared_dir(),
+            notifications: default_notifications(),
+            icon: None,
+            frontend: Frontend::default(),
         }
     }
 }
 
+/// Which Linux input method frontend hosts Rime; only consulted on Linux.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Frontend {
+    #[default]
+    Auto,
+    Fcitx5,
+    Ibus,
+}
+
+fn default_notifications() -> bool {
+    true
+}
+
+#[cfg(target_os = "windows")]
+fn default_user_dir() -> String {
+    env::var("APPDATA")
+        .map(|appdata| Path::new(&appdata).join("Rime").to_string_lossy().into_owned())
+        .unwrap_or_else(|e| {
+            error!("{e}");
+            String::from("C:/Users/Default/AppData/Roaming/Rime")
+        })
+}
+
+#[cfg(target_os = "linux")]
+fn default_user_dir() -> String {
+    dirs::home_dir()
+        .map(|home| {
+            let fcitx5_dir = home.join(".local/share/fcitx5/rime");
+            if fcitx5_dir.exists() {
+                fcitx5_dir
+            } else {
+                home.join(".config/ibus/rime")
+            }
+            .to_string_lossy()
+            .into_owned()
+        })
+        .unwrap_or_else(|| {
+            error!("failed to resolve home directory");
+            String::from(".config/ibus/rime")
+        })
+}
+
+/// Shared Rime data directory (preset schemas, `default.yaml`) consulted by
+/// `rime_deployer --build`. A separate setting from `root`: on Linux `root`
+/// is not a Rime data path at all, while on Windows this defaults to the same
+/// value as `root`, the Weasel install directory.
+#[cfg(target_os = "windows")]
+fn default_shared_dir() -> String {
+    default_rime_root()
+}
+
+#[cfg(target_os = "linux")]
+fn default_shared_dir() -> String {
+    "/usr/share/rime-data".to_string()
+}
+
 const NAME: &str = "Rime 工具箱";
 const ICON_BYTES: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/icon.png"));
 static CONFIG: LazyLock<ToolConfig> = LazyLock::new(|| load_config());
@@ -175,46 +286,114 @@ fn load_config() -> ToolConfig {
     toml::from_str(&config_str).unwrap_or_default()
 }
 
+fn notify_toast(summary: &str, body: &str) {
+    if !CONFIG.notifications {
+        return;
+    }
+    if let Err(e) = Notification::new().summary(summary).body(body).show() {
+        warn!("failed to show notification: {e}");
+    }
+}
+
+/// Returns the last few lines of a finished child's stderr, so a failure
+/// notification can hint at what actually went wrong.
+fn stderr_tail(stderr: &[u8]) -> String {
+    String::from_utf8_lossy(stderr)
+        .lines()
+        .rev()
+        .take(5)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(target_os = "windows")]
 fn rime_redeploy() {
-    #[cfg(target_os = "windows")]
     let args = vec!["/deploy"];
-
-    #[cfg(target_os = "windows")]
     let deploy = "WeaselDeployer.exe";
 
     let deployer = Path::new(&CONFIG.root).join(deploy);
     debug!("redeploy! {deployer:?}");
+    notify_toast("Rime 工具箱", "部署中…");
     thread::spawn(move || {
-        let redeploy = Command::new(deployer.clone()).args(args).spawn();
-        if let Err(e) = redeploy {
-            error!(
-                "failed to deploy. {:?} {}",
-                deployer.to_str().unwrap_or_default(),
-                e
-            );
+        let child = Command::new(deployer.clone())
+            .args(args)
+            .stderr(Stdio::piped())
+            .spawn();
+        match child {
+            Ok(child) => match child.wait_with_output() {
+                Ok(output) if output.status.success() => {
+                    notify_toast("Rime 工具箱", "部署完成");
+                }
+                Ok(output) => {
+                    let tail = stderr_tail(&output.stderr);
+                    error!("deploy exited with {}. {tail}", output.status);
+                    notify_toast("Rime 工具箱", &format!("部署失败: {tail}"));
+                }
+                Err(e) => {
+                    error!("failed to wait for deploy: {e}");
+                    notify_toast("Rime 工具箱", &format!("部署失败: {e}"));
+                }
+            },
+            Err(e) => {
+                error!(
+                    "failed to deploy. {:?} {}",
+                    deployer.to_str().unwrap_or_default(),
+                    e
+                );
+                notify_toast("Rime 工具箱", &format!("部署失败: {e}"));
+            }
         }
     });
 }
-fn rime_start_service() {
-    #[cfg(target_os = "windows")]
+/// Starts `WeaselServer.exe`. `quiet` suppresses toast notifications, so the
+/// watchdog's automatic restarts don't spam the user during a backoff loop.
+#[cfg(target_os = "windows")]
+fn rime_start_service(quiet: bool) {
     let args = vec!["/restart"];
-
-    #[cfg(target_os = "windows")]
     let server = "WeaselServer.exe";
 
     let server = Path::new(&CONFIG.root).join(server);
 
     thread::spawn(move || {
-        let redeploy = Command::new(server.clone()).args(args).spawn();
-        if let Err(e) = redeploy {
-            error!(
-                "failed to restart. {} {}",
-                server.to_str().unwrap_or_default(),
-                e
-            );
+        let child = Command::new(server.clone())
+            .args(args)
+            .stderr(Stdio::piped())
+            .spawn();
+        match child {
+            Ok(child) => match child.wait_with_output() {
+                Ok(output) if output.status.success() => {
+                    if !quiet {
+                        notify_toast("Rime 工具箱", "算法服务已启动");
+                    }
+                }
+                Ok(output) => {
+                    let tail = stderr_tail(&output.stderr);
+                    error!("start service exited with {}. {tail}", output.status);
+                    if !quiet {
+                        notify_toast("Rime 工具箱", &format!("启动失败: {tail}"));
+                    }
+                }
+                Err(e) => {
+                    error!("failed to wait for service start: {e}");
+                    if !quiet {
+                        notify_toast("Rime 工具箱", &format!("启动失败: {e}"));
+                    }
+                }
+            },
+            Err(e) => {
+                error!(
+                    "failed to restart. {} {}",
+                    server.to_str().unwrap_or_default(),
+                    e
+                );
+                if !quiet {
+                    notify_toast("Rime 工具箱", &format!("启动失败: {e}"));
+                }
+            }
         }
     });
 }
+#[cfg(target_os = "windows")]
 fn rime_stop_service() {
     let s = System::new_with_specifics(
         RefreshKind::nothing().with_processes(ProcessRefreshKind::everything()),
@@ -223,7 +402,9 @@ fn rime_stop_service() {
     for p in ps {
         p.kill();
     }
+    notify_toast("Rime 工具箱", "算法服务已停止");
 }
+#[cfg(target_os = "windows")]
 fn get_service_status() -> bool {
     let s = System::new_with_specifics(
         RefreshKind::nothing().with_processes(ProcessRefreshKind::everything()),
@@ -231,12 +412,64 @@ fn get_service_status() -> bool {
     let ps = s.processes_by_name(OsStr::new("WeaselServer.exe"));
     ps.count() > 0
 }
-fn update_service_status(service_item: &CheckMenuItem) {
-    service_item.set_checked(get_service_status());
+
+#[cfg(target_os = "linux")]
+fn rime_redeploy() {
+    notify_toast("Rime 工具箱", "部署中…");
+    thread::spawn(|| match linux::redeploy(CONFIG.frontend, &CONFIG.user_dir, &CONFIG.shared_dir) {
+        Ok(()) => notify_toast("Rime 工具箱", "部署完成"),
+        Err(e) => {
+            error!("failed to deploy: {e}");
+            notify_toast("Rime 工具箱", &format!("部署失败: {e}"));
+        }
+    });
+}
+/// Starts the Rime-hosting frontend process. `quiet` suppresses toast
+/// notifications, so the watchdog's automatic restarts don't spam the user
+/// during a backoff loop.
+#[cfg(target_os = "linux")]
+fn rime_start_service(quiet: bool) {
+    thread::spawn(move || match linux::start_service(CONFIG.frontend) {
+        Ok(()) => {
+            if !quiet {
+                notify_toast("Rime 工具箱", "算法服务已启动");
+            }
+        }
+        Err(e) => {
+            error!("failed to start service: {e}");
+            if !quiet {
+                notify_toast("Rime 工具箱", &format!("启动失败: {e}"));
+            }
+        }
+    });
+}
+#[cfg(target_os = "linux")]
+fn rime_stop_service() {
+    linux::stop_service(CONFIG.frontend);
+    notify_toast("Rime 工具箱", "算法服务已停止");
+}
+#[cfg(target_os = "linux")]
+fn get_service_status() -> bool {
+    linux::get_service_status(CONFIG.frontend)
+}
+fn update_service_status(
+    service_item: &CheckMenuItem,
+    tray_icon: &Option<TrayIcon>,
+    icon_running: &Icon,
+    icon_stopped: &Icon,
+) {
+    let running = get_service_status();
+    service_item.set_checked(running);
+    if let Some(tray_icon) = tray_icon {
+        let icon = if running { icon_running } else { icon_stopped };
+        if let Err(e) = tray_icon.set_icon(Some(icon.clone())) {
+            error!("failed to update tray icon: {e}");
+        }
+    }
 }
 fn toggle_service(checked: bool) {
     if checked {
-        rime_start_service();
+        rime_start_service(false);
     } else {
         rime_stop_service();
     }
@@ -254,29 +487,56 @@ macro_rules! panic_if_err {
 struct TrayIconContext {
     tray_icon: Option<TrayIcon>,
     service: CheckMenuItem,
+    auto_deploy: CheckMenuItem,
+    autostart: CheckMenuItem,
+    watchdog: CheckMenuItem,
+    icon_running: Icon,
+    icon_stopped: Icon,
 }
 
 fn create_tray(proxy: EventLoopProxy<TrayUserEvent>) -> TrayIconContext {
-    let icon = ICON_BYTES;
     let tray_menu = Menu::new();
 
-    let icon_about = load_icon(icon);
-    let icon_exe = icon_about.clone();
+    let icon_running_rgba = load_configured_icon();
+    let icon_stopped_rgba = icon_running_rgba.stopped_variant();
     let icon_about = panic_if_err!(
-        MIcon::from_rgba(icon_about.rgba, icon_about.width, icon_about.height),
+        MIcon::from_rgba(
+            icon_running_rgba.rgba.clone(),
+            icon_running_rgba.width,
+            icon_running_rgba.height
+        ),
+        "Failed to load icon. {}"
+    );
+    let icon_running = panic_if_err!(
+        Icon::from_rgba(
+            icon_running_rgba.rgba.clone(),
+            icon_running_rgba.width,
+            icon_running_rgba.height
+        ),
         "Failed to load icon. {}"
     );
-    let icon_exe = panic_if_err!(
-        Icon::from_rgba(icon_exe.rgba, icon_exe.width, icon_exe.height),
+    let icon_stopped = panic_if_err!(
+        Icon::from_rgba(
+            icon_stopped_rgba.rgba.clone(),
+            icon_stopped_rgba.width,
+            icon_stopped_rgba.height
+        ),
         "Failed to load icon. {}"
     );
+    let icon_exe = icon_running.clone();
 
     let service = CheckMenuItem::new("算法服务", true, true, None);
     let redeploy = MenuItem::new("重新部署", true, None);
+    let auto_deploy = CheckMenuItem::new("自动部署", true, true, None);
+    let autostart = CheckMenuItem::new("开机自启", true, autostart::is_enabled(), None);
+    let watchdog = CheckMenuItem::new("看门狗", true, true, None);
     let quit = MenuItem::new("退出", true, None);
     tray_menu.append_items(&[
         &service,
         &redeploy,
+        &auto_deploy,
+        &autostart,
+        &watchdog,
         &PredefinedMenuItem::separator(),
         &PredefinedMenuItem::about(
             Some("关于"),
@@ -305,11 +565,17 @@ fn create_tray(proxy: EventLoopProxy<TrayUserEvent>) -> TrayIconContext {
     let quit_id = quit.id().clone();
     let redeploy_id = redeploy.id().clone();
     let service_id = service.id().clone();
+    let auto_deploy_id = auto_deploy.id().clone();
+    let autostart_id = autostart.id().clone();
+    let watchdog_id = watchdog.id().clone();
     debug!(
-        "ids: quit: {:?} redeploy: {:?} service: {:?}",
-        quit_id, redeploy_id, service_id
+        "ids: quit: {:?} redeploy: {:?} service: {:?} auto_deploy: {:?} autostart: {:?} watchdog: {:?}",
+        quit_id, redeploy_id, service_id, auto_deploy_id, autostart_id, watchdog_id
     );
     let proxy_menu = proxy.clone();
+    let auto_deploy_handler = auto_deploy.clone();
+    let autostart_handler = autostart.clone();
+    let watchdog_handler = watchdog.clone();
     MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
         trace!("menu {event:?}");
         if event.id == quit_id {
@@ -318,6 +584,16 @@ fn create_tray(proxy: EventLoopProxy<TrayUserEvent>) -> TrayIconContext {
             rime_redeploy();
         } else if event.id == service_id {
             proxy_menu.send_event(TrayUserEvent::ServiceClicked);
+        } else if event.id == auto_deploy_id {
+            proxy_menu.send_event(TrayUserEvent::ToggleAutoDeploy(
+                auto_deploy_handler.is_checked(),
+            ));
+        } else if event.id == autostart_id {
+            proxy_menu.send_event(TrayUserEvent::ToggleAutostart(
+                autostart_handler.is_checked(),
+            ));
+        } else if event.id == watchdog_id {
+            proxy_menu.send_event(TrayUserEvent::ToggleWatchdog(watchdog_handler.is_checked()));
         }
     }));
 
@@ -339,7 +615,15 @@ fn create_tray(proxy: EventLoopProxy<TrayUserEvent>) -> TrayIconContext {
         }
     }));
 
-    TrayIconContext { tray_icon, service }
+    TrayIconContext {
+        tray_icon,
+        service,
+        auto_deploy,
+        autostart,
+        watchdog,
+        icon_running,
+        icon_stopped,
+    }
 }
 
 fn start() {
@@ -348,8 +632,26 @@ fn start() {
     let TrayIconContext {
         mut tray_icon,
         service,
+        auto_deploy,
+        autostart,
+        watchdog,
+        icon_running,
+        icon_stopped,
     } = create_tray(event_loop.create_proxy());
 
+    // Reflect the real service status immediately; otherwise the tray shows
+    // the "running" icon on launch until the first click.
+    update_service_status(&service, &tray_icon, &icon_running, &icon_stopped);
+
+    let config_watcher = ConfigWatcher::new(&CONFIG.user_dir, AUTO_DEPLOY_QUIET_PERIOD, || {
+        rime_redeploy();
+    });
+    if config_watcher.is_none() {
+        auto_deploy.set_checked(false);
+    }
+
+    let service_watchdog = Watchdog::spawn(get_service_status, || rime_start_service(true));
+
     // filter all device event, maybe change to unfocused, if add another feature.
     event_loop.set_device_event_filter(DeviceEventFilter::Always);
     event_loop.run(move |event, _, control_flow| {
@@ -368,11 +670,27 @@ fn start() {
                 *control_flow = ControlFlow::Exit;
             }
             UserEvent(TrayUserEvent::IconClicked) => {
-                update_service_status(&service);
+                update_service_status(&service, &tray_icon, &icon_running, &icon_stopped);
             }
             UserEvent(TrayUserEvent::ServiceClicked) => {
-                toggle_service(service.is_checked());
-                update_service_status(&service);
+                let checked = service.is_checked();
+                service_watchdog.set_desired_running(checked);
+                toggle_service(checked);
+                update_service_status(&service, &tray_icon, &icon_running, &icon_stopped);
+            }
+            UserEvent(TrayUserEvent::ToggleAutoDeploy(enabled)) => {
+                debug!("auto deploy toggled: {enabled}");
+                if let Some(w) = &config_watcher {
+                    w.set_enabled(enabled);
+                }
+            }
+            UserEvent(TrayUserEvent::ToggleAutostart(enabled)) => {
+                debug!("autostart toggled: {enabled}");
+                autostart::set_enabled_or_log(enabled, &autostart);
+            }
+            UserEvent(TrayUserEvent::ToggleWatchdog(enabled)) => {
+                debug!("watchdog toggled: {enabled}");
+                service_watchdog.set_enabled(enabled);
             }
             _ => {}
         }
@@ -406,6 +724,78 @@ fn main() {
     start();
 }
 
+/// Size (in pixels, square) the tray icon is rasterized at when loaded from
+/// a vector source such as SVG.
+const TRAY_ICON_SIZE: u32 = 32;
+
+/// Loads the configured tray icon, falling back to the embedded asset if
+/// `ToolConfig.icon` is unset or fails to load for any reason.
+fn load_configured_icon() -> RgbaIcon {
+    CONFIG
+        .icon
+        .as_ref()
+        .and_then(|path| {
+            fs::read(path)
+                .inspect_err(|e| error!("failed to read icon {path}: {e}"))
+                .ok()
+                .map(|bytes| (path, bytes))
+        })
+        .and_then(|(path, bytes)| {
+            let icon = if path.to_lowercase().ends_with(".svg") {
+                load_svg_icon(&bytes)
+            } else {
+                load_raster_icon(&bytes)
+            };
+            icon.inspect_err(|e| error!("failed to load icon {path}: {e}")).ok()
+        })
+        .unwrap_or_else(|| load_icon(ICON_BYTES))
+}
+
+/// Loads a PNG/ICO/BMP/etc. icon, sniffing the format from its contents.
+fn load_raster_icon(bytes: &[u8]) -> anyhow::Result<RgbaIcon> {
+    let image = image::load_from_memory(bytes)
+        .with_context(|| "failed to decode icon")?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    Ok(RgbaIcon {
+        rgba: image.into_raw(),
+        width,
+        height,
+    })
+}
+
+/// Rasterizes an SVG icon to RGBA at `TRAY_ICON_SIZE`.
+fn load_svg_icon(bytes: &[u8]) -> anyhow::Result<RgbaIcon> {
+    let tree = usvg::Tree::from_data(bytes, &usvg::Options::default())
+        .with_context(|| "failed to parse svg")?;
+    let mut pixmap = tiny_skia::Pixmap::new(TRAY_ICON_SIZE, TRAY_ICON_SIZE)
+        .with_context(|| "failed to allocate pixmap")?;
+    let size = tree.size();
+    let scale = TRAY_ICON_SIZE as f32 / size.width().max(size.height());
+    let transform = tiny_skia::Transform::from_scale(scale, scale);
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    // tiny_skia stores premultiplied alpha, but Icon::from_rgba/MIcon::from_rgba
+    // expect straight alpha, so demultiply each pixel before handing it off.
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let a = p.alpha();
+            if a == 0 {
+                [0, 0, 0, 0]
+            } else {
+                let unmul = |c: u8| ((c as u16 * 255 + a as u16 / 2) / a as u16) as u8;
+                [unmul(p.red()), unmul(p.green()), unmul(p.blue()), a]
+            }
+        })
+        .collect();
+    Ok(RgbaIcon {
+        rgba,
+        width: TRAY_ICON_SIZE,
+        height: TRAY_ICON_SIZE,
+    })
+}
+
 fn load_icon(icon: &[u8]) -> RgbaIcon {
     let image =
         panic_if_err!(image::load_from_memory(icon), "Faild to load icon: {:?}").into_rgba8();
@@ -0,0 +1,91 @@
+use log::{debug, warn};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// How long the service has to stay alive before a restart is considered
+/// stable and the backoff counter resets.
+const STABILITY_WINDOW: Duration = Duration::from_secs(30);
+
+/// Supervises the algorithm service, restarting it with exponential backoff
+/// whenever it dies while the user wants it running.
+///
+/// `desired_running` tracks user intent separately from the observed process
+/// status, so the watchdog never fights a deliberate stop.
+pub(crate) struct Watchdog {
+    enabled: Arc<AtomicBool>,
+    desired_running: Arc<AtomicBool>,
+}
+
+impl Watchdog {
+    pub(crate) fn spawn<S, R>(get_status: S, restart: R) -> Self
+    where
+        S: Fn() -> bool + Send + 'static,
+        R: Fn() + Send + 'static,
+    {
+        let enabled = Arc::new(AtomicBool::new(true));
+        let desired_running = Arc::new(AtomicBool::new(true));
+
+        let enabled_thread = enabled.clone();
+        let desired_running_thread = desired_running.clone();
+        thread::spawn(move || {
+            let mut consecutive_failures: u32 = 0;
+            let mut alive_since: Option<Instant> = None;
+            let mut next_attempt_at = Instant::now();
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                if !enabled_thread.load(Ordering::SeqCst)
+                    || !desired_running_thread.load(Ordering::SeqCst)
+                {
+                    alive_since = None;
+                    continue;
+                }
+
+                if get_status() {
+                    let since = *alive_since.get_or_insert_with(Instant::now);
+                    if since.elapsed() >= STABILITY_WINDOW && consecutive_failures > 0 {
+                        debug!("service stable for {STABILITY_WINDOW:?}, resetting backoff");
+                        consecutive_failures = 0;
+                    }
+                    continue;
+                }
+
+                alive_since = None;
+                if Instant::now() < next_attempt_at {
+                    continue;
+                }
+
+                warn!("service not running but desired, attempt {consecutive_failures} restart");
+                restart();
+                let backoff = BASE_BACKOFF
+                    .saturating_mul(1 << consecutive_failures.min(16))
+                    .min(MAX_BACKOFF);
+                next_attempt_at = Instant::now() + backoff;
+                consecutive_failures = consecutive_failures.saturating_add(1);
+            }
+        });
+
+        Self {
+            enabled,
+            desired_running,
+        }
+    }
+
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Records whether the user wants the service running, independent of
+    /// its currently observed status.
+    pub(crate) fn set_desired_running(&self, desired_running: bool) {
+        self.desired_running.store(desired_running, Ordering::SeqCst);
+    }
+}
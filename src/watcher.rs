@@ -0,0 +1,100 @@
+use log::{debug, error, trace};
+use notify::{RecursiveMode, Watcher};
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often the debounce thread wakes up to check for a quiet period.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Dictionary/schema file extensions that should trigger a redeploy.
+fn is_watched_file(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(n) => n,
+        None => return false,
+    };
+    name.ends_with(".yaml") || name.ends_with(".custom.yaml") || name.ends_with(".txt")
+}
+
+/// Watches `dir` for dictionary/schema changes and calls `on_change` after
+/// `quiet_period` has elapsed with no new events, so a burst of saves from an
+/// editor only triggers a single redeploy.
+pub(crate) struct ConfigWatcher {
+    enabled: Arc<AtomicBool>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    pub(crate) fn new<F>(dir: impl AsRef<Path>, quiet_period: Duration, on_change: F) -> Option<Self>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let dir = dir.as_ref().to_owned();
+        let enabled = Arc::new(AtomicBool::new(true));
+        // `Some(t)` means a watched file changed at `t` and no redeploy has
+        // fired for it yet; the poll thread only clears it back to `None`
+        // while still holding the lock it read `t` under, so an event that
+        // arrives between the elapsed check and the clear is never lost.
+        let last_event: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+
+        let last_event_cb = last_event.clone();
+        let enabled_cb = enabled.clone();
+        let mut watcher = match notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if !enabled_cb.load(Ordering::SeqCst) {
+                return;
+            }
+            match res {
+                Ok(event) => {
+                    if event.paths.iter().any(|p| is_watched_file(p)) {
+                        trace!("watcher event {event:?}");
+                        *last_event_cb.lock().unwrap() = Some(Instant::now());
+                    }
+                }
+                Err(e) => error!("watch error: {e}"),
+            }
+        }) {
+            Ok(w) => w,
+            Err(e) => {
+                error!("failed to create config watcher: {e}");
+                return None;
+            }
+        };
+
+        if let Err(e) = watcher.watch(&dir, RecursiveMode::Recursive) {
+            error!("failed to watch {dir:?}: {e}");
+            return None;
+        }
+        debug!("watching {dir:?} for rime config changes");
+
+        thread::spawn(move || loop {
+            thread::sleep(POLL_INTERVAL);
+            let mut last_event = last_event.lock().unwrap();
+            let Some(t) = *last_event else {
+                continue;
+            };
+            let quiet_for = t.elapsed();
+            if quiet_for < quiet_period {
+                continue;
+            }
+            *last_event = None;
+            drop(last_event);
+            debug!("config quiet for {quiet_for:?}, triggering redeploy");
+            on_change();
+        });
+
+        Some(Self {
+            enabled,
+            _watcher: watcher,
+        })
+    }
+
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+}